@@ -1,6 +1,20 @@
 //! This module contains the `EvalContext` methods for executing a single step of the interpreter.
 //!
-//! The main entry point is the `step` method.
+//! The main entry point is the `step` method. Before and after each statement and terminator,
+//! `step` invokes the corresponding `Machine` hook (`before_statement`, `after_statement`,
+//! `before_terminator`, `after_terminator`), so that downstream machines can observe the
+//! interpreter's progress -- e.g. to build coverage collectors or step tracers -- without
+//! having to fork this loop. The default hook implementations are no-ops.
+//!
+//! `inc_step_counter_and_detect_loops` also enforces `Machine::MAX_STEPS`, an optional hard
+//! budget on the number of executed terminators. It defaults to `None` (unlimited) so CTFE's
+//! behavior is unchanged; machines that want a deterministic abort instead of relying on the
+//! loop detector's state-hash heuristic can set it.
+//!
+//! `InlineAsm` statements are routed through `Machine::eval_inline_asm`, whose default
+//! implementation rejects them with the `InlineAsm` error (CTFE's long-standing behavior);
+//! machines that understand particular asm templates can override it to write results into
+//! the output places instead.
 
 use rustc::mir;
 use rustc::ty::layout::LayoutOf;
@@ -14,6 +28,16 @@ impl<'a, 'mir, 'tcx, M: Machine<'mir, 'tcx>> EvalContext<'a, 'mir, 'tcx, M> {
         /// Should be a power of two for performance reasons.
         const DETECTOR_SNAPSHOT_PERIOD: isize = 256;
 
+        // Give embedders a deterministic abort path for divergent or adversarial constants,
+        // instead of relying solely on the (cheaper, but probabilistic) state-hash loop detector
+        // below. `M::MAX_STEPS` defaults to `None` (unlimited) to keep CTFE's existing behavior.
+        self.step_count += 1;
+        if let Some(limit) = M::MAX_STEPS {
+            if self.step_count > limit {
+                return err!(StepLimitReached);
+            }
+        }
+
         {
             let steps = &mut self.steps_since_detector_enabled;
 
@@ -54,7 +78,9 @@ impl<'a, 'mir, 'tcx, M: Machine<'mir, 'tcx>> EvalContext<'a, 'mir, 'tcx, M> {
 
         if let Some(stmt) = basic_block.statements.get(stmt_id) {
             assert_eq!(old_frames, self.cur_frame());
+            M::before_statement(self, stmt)?;
             self.statement(stmt)?;
+            M::after_statement(self, stmt)?;
             return Ok(true);
         }
 
@@ -62,7 +88,9 @@ impl<'a, 'mir, 'tcx, M: Machine<'mir, 'tcx>> EvalContext<'a, 'mir, 'tcx, M> {
 
         let terminator = basic_block.terminator();
         assert_eq!(old_frames, self.cur_frame());
+        M::before_terminator(self, terminator)?;
         self.terminator(terminator)?;
+        M::after_terminator(self, terminator)?;
         Ok(true)
     }
 
@@ -120,7 +148,9 @@ impl<'a, 'mir, 'tcx, M: Machine<'mir, 'tcx>> EvalContext<'a, 'mir, 'tcx, M> {
             // size of MIR constantly.
             Nop => {}
 
-            InlineAsm { .. } => return err!(InlineAsm),
+            InlineAsm { ref asm, ref outputs, ref inputs } => {
+                M::eval_inline_asm(self, asm, outputs, inputs)?
+            }
         }
 
         self.stack[frame_idx].stmt += 1;